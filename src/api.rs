@@ -6,6 +6,7 @@
 use identity::Identity;
 use libc::*;
 use log;
+use mnemonic;
 use proteus::keys::{self, IdentityKeyPair, PreKey, PreKeyBundle, PreKeyId};
 use proteus::message::Envelope;
 use proteus::session::{DecryptError, PreKeyStore, Session};
@@ -15,11 +16,16 @@ use std::boxed::Box;
 use std::error::Error;
 use std::ffi::{CStr, CString, NulError};
 use std::path::Path;
+use std::process;
 use std::slice;
 use std::str;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
 use std::u16;
 use store::api::{Store, StorageError, StorageResult};
 use store::file::FileStore;
+use store::mem::MemStore;
 
 /// Variant of std::try! that returns the unwrapped error.
 macro_rules! try_unwrap {
@@ -92,32 +98,120 @@ fn cbox_file_open_with(c_path:   *const c_char,
         Identity::Sec(i) => i.into_owned(),
         Identity::Pub(_) => return CBoxResult::IdentityError
     };
-    match try_unwrap!(store.load_identity()) {
+    match reconcile_identity(&store, ident, c_mode) {
+        Ok(ident) => {
+            *c_box = Box::into_raw(Box::new(CBox { store: Box::new(store), ident: ident }));
+            CBoxResult::Success
+        }
+        Err(e) => e
+    }
+}
+
+#[no_mangle]
+pub unsafe extern
+fn cbox_file_open_from_phrase(c_path:       *const c_char,
+                              c_phrase:     *const c_char,
+                              c_passphrase: *const c_char,
+                              c_mode:       CBoxIdentityMode,
+                              c_box:        *mut *mut CBox) -> CBoxResult
+{
+    proteus::init();
+    let name       = try_unwrap!(str::from_utf8(CStr::from_ptr(c_path).to_bytes()));
+    let path       = Path::new(name);
+    let phrase     = try_unwrap!(str::from_utf8(CStr::from_ptr(c_phrase).to_bytes()));
+    let passphrase = try_unwrap!(str::from_utf8(CStr::from_ptr(c_passphrase).to_bytes()));
+
+    if mnemonic::validate_mnemonic(phrase).is_err() {
+        return CBoxResult::InvalidPhrase
+    }
+
+    let store = try_unwrap!(FileStore::new(path));
+    let seed  = mnemonic::mnemonic_to_seed(phrase, passphrase);
+    let ident = IdentityKeyPair::new_from_seed(&seed[..32]);
+
+    match reconcile_identity(&store, ident, c_mode) {
+        Ok(ident) => {
+            *c_box = Box::into_raw(Box::new(CBox { store: Box::new(store), ident: ident }));
+            CBoxResult::Success
+        }
+        Err(e) => e
+    }
+}
+
+/// Open a `CBox` backed by an in-memory `MemStore` instead of a
+/// `FileStore`, for callers that don't want or can't use the
+/// filesystem. Behaves exactly like `cbox_file_open_with` otherwise,
+/// including identity reconciliation, except the store (and everything
+/// in it) disappears once the `CBox` is closed.
+#[no_mangle]
+pub unsafe extern
+fn cbox_open_memory(c_id:     *const uint8_t,
+                    c_id_len: size_t,
+                    c_mode:   CBoxIdentityMode,
+                    c_box:    *mut *mut CBox) -> CBoxResult
+{
+    proteus::init();
+    let store = MemStore::new();
+    let ident = match try_unwrap!(dec_raw(&c_id, c_id_len as usize, Identity::deserialise)) {
+        Identity::Sec(i) => i.into_owned(),
+        Identity::Pub(_) => return CBoxResult::IdentityError
+    };
+    match reconcile_identity(&store, ident, c_mode) {
+        Ok(ident) => {
+            *c_box = Box::into_raw(Box::new(CBox { store: Box::new(store), ident: ident }));
+            CBoxResult::Success
+        }
+        Err(e) => e
+    }
+}
+
+#[no_mangle]
+pub unsafe extern
+fn cbox_generate_phrase(c_entropy_bits: size_t, c_phrase: *mut *mut CBoxVec) -> CBoxResult {
+    let entropy = keys::rand_bytes(c_entropy_bits as usize / 8);
+    let phrase  = match mnemonic::entropy_to_mnemonic(&entropy) {
+        Ok(p)  => p,
+        Err(_) => return CBoxResult::InvalidPhrase
+    };
+    *c_phrase = CBoxVec::from_vec(phrase.into_bytes());
+    CBoxResult::Success
+}
+
+/// Reconcile a just-derived or just-decoded identity `ident` against
+/// whatever identity (if any) is already present in `store`, persisting
+/// it according to `mode`. Shared by `cbox_file_open_with`,
+/// `cbox_file_open_from_phrase` and `cbox_open_memory` so opening a
+/// store behaves the same regardless of how the identity was obtained
+/// or which backend holds it.
+fn reconcile_identity<S: Store<Error=StorageError>>(store: &S,
+                                                    ident: IdentityKeyPair,
+                                                    mode:  CBoxIdentityMode) -> Result<IdentityKeyPair, CBoxResult>
+{
+    match try!(store.load_identity()) {
         Some(Identity::Sec(local)) => {
             if ident.public_key != local.public_key {
-                return CBoxResult::IdentityError
+                return Err(CBoxResult::IdentityError)
             }
-            if c_mode == CBoxIdentityMode::Public {
-                try_unwrap!(store.save_identity(&Identity::Pub(Cow::Borrowed(&ident.public_key))))
+            if mode == CBoxIdentityMode::Public {
+                try!(store.save_identity(&Identity::Pub(Cow::Borrowed(&ident.public_key))))
             }
         }
         Some(Identity::Pub(local)) => {
             if ident.public_key != *local {
-                return CBoxResult::IdentityError
+                return Err(CBoxResult::IdentityError)
             }
-            if c_mode == CBoxIdentityMode::Complete {
-                try_unwrap!(store.save_identity(&Identity::Sec(Cow::Borrowed(&ident))))
+            if mode == CBoxIdentityMode::Complete {
+                try!(store.save_identity(&Identity::Sec(Cow::Borrowed(&ident))))
             }
         }
-        None => match c_mode {
+        None => match mode {
             CBoxIdentityMode::Public =>
-                try_unwrap!(store.save_identity(&Identity::Pub(Cow::Borrowed(&ident.public_key)))),
+                try!(store.save_identity(&Identity::Pub(Cow::Borrowed(&ident.public_key)))),
             CBoxIdentityMode::Complete =>
-                try_unwrap!(store.save_identity(&Identity::Sec(Cow::Borrowed(&ident))))
+                try!(store.save_identity(&Identity::Sec(Cow::Borrowed(&ident))))
         }
     }
-    *c_box = Box::into_raw(Box::new(CBox { store: Box::new(store), ident: ident }));
-    CBoxResult::Success
+    Ok(ident)
 }
 
 #[no_mangle]
@@ -128,12 +222,101 @@ fn cbox_identity_copy(b: *const CBox, c_ident: *mut *mut CBoxVec) -> CBoxResult
     CBoxResult::Success
 }
 
+/// Like `cbox_identity_copy`, but serialises only the public half of the
+/// identity, as an `Identity::Pub`. Safe to hand to a peer that only
+/// needs to verify signatures or fingerprints against this box's
+/// identity — unlike `cbox_identity_copy`, it never exposes the secret
+/// key.
+#[no_mangle]
+pub unsafe extern
+fn cbox_identity_copy_public(b: *const CBox, c_ident: *mut *mut CBoxVec) -> CBoxResult {
+    let i = try_unwrap!(Identity::Pub(Cow::Borrowed(&(*b).ident.public_key)).serialise());
+    *c_ident = CBoxVec::from_vec(i);
+    CBoxResult::Success
+}
+
 #[no_mangle]
 pub unsafe extern
 fn cbox_close(b: *mut CBox) {
     Box::from_raw(b);
 }
 
+/// The character set `cbox_fingerprint_local` / `fingerprint()` encodes
+/// into, i.e. lower-case hex.
+static FINGERPRINT_ALPHABET: &'static str = "0123456789abcdef";
+
+/// The length in hex characters of a fingerprint, i.e. a SHA-256 digest.
+/// A prefix longer than this can never match, regardless of how many
+/// attempts are allowed.
+const FINGERPRINT_LEN: usize = 64;
+
+/// Search for an `IdentityKeyPair` whose fingerprint starts with
+/// `c_prefix`, spreading the search across `c_num_threads` workers. On
+/// success `c_identity` receives a serialised `Identity::Sec`, ready to
+/// hand to `cbox_file_open_with`. Returns `CBoxResult::InvalidPrefix` if
+/// `c_prefix` is empty, longer than a fingerprint, or contains characters
+/// outside the fingerprint alphabet, and `CBoxResult::PrefixNotFound` if
+/// `c_max_attempts` (0 = unbounded) is exhausted before a match turns up.
+#[no_mangle]
+pub unsafe extern
+fn cbox_identity_generate_prefix(c_prefix:       *const c_char,
+                                 c_prefix_len:   size_t,
+                                 c_num_threads:  size_t,
+                                 c_max_attempts: uint64_t,
+                                 c_identity:     *mut *mut CBoxVec) -> CBoxResult
+{
+    let bytes  = slice::from_raw_parts(c_prefix as *const u8, c_prefix_len as usize);
+    let prefix = try_unwrap!(str::from_utf8(bytes)).to_owned();
+
+    if prefix.is_empty()
+        || prefix.len() > FINGERPRINT_LEN
+        || !prefix.chars().all(|c| FINGERPRINT_ALPHABET.contains(c))
+    {
+        return CBoxResult::InvalidPrefix
+    }
+
+    let num_threads  = if c_num_threads == 0 { 1 } else { c_num_threads as usize };
+    let max_attempts = c_max_attempts as usize;
+
+    let found    = Arc::new(Mutex::new(None));
+    let stop     = Arc::new(AtomicBool::new(false));
+    let attempts = Arc::new(AtomicUsize::new(0));
+
+    let workers: Vec<_> = (0..num_threads).map(|_| {
+        let prefix   = prefix.clone();
+        let found    = found.clone();
+        let stop     = stop.clone();
+        let attempts = attempts.clone();
+        thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                if max_attempts > 0 && attempts.fetch_add(1, Ordering::Relaxed) >= max_attempts {
+                    stop.store(true, Ordering::Relaxed);
+                    break
+                }
+                let candidate = IdentityKeyPair::new();
+                if candidate.public_key.fingerprint().starts_with(&prefix) {
+                    *found.lock().unwrap() = Some(candidate);
+                    stop.store(true, Ordering::Relaxed);
+                    break
+                }
+            }
+        })
+    }).collect();
+
+    for w in workers {
+        let _ = w.join();
+    }
+
+    match found.lock().unwrap().take() {
+        Some(ident) => {
+            let bytes = try_unwrap!(Identity::Sec(Cow::Owned(ident)).serialise());
+            *c_identity = CBoxVec::from_vec(bytes);
+            CBoxResult::Success
+        }
+        None => CBoxResult::PrefixNotFound
+    }
+}
+
 // Prekeys //////////////////////////////////////////////////////////////////
 
 #[no_mangle]
@@ -342,6 +525,209 @@ fn cbox_fingerprint_remote(s: *const CBoxSession, buf: *mut *mut CBoxVec) {
     *buf = CBoxVec::from_vec(fp.into_bytes());
 }
 
+// Signing ////////////////////////////////////////////////////////////////////
+
+/// Produce a detached signature over `data` using the box's long-term
+/// identity secret key, so applications can authenticate payloads (e.g.
+/// prekey bundles or profile blobs) without establishing a session.
+#[no_mangle]
+pub unsafe extern
+fn cbox_sign(c_box:      *const CBox,
+            c_data:     *const uint8_t,
+            c_data_len: size_t,
+            c_sig:      *mut *mut CBoxVec) -> CBoxResult
+{
+    let cbox = &*c_box;
+    let data = slice::from_raw_parts(c_data, c_data_len as usize);
+    let sig  = try_unwrap!(cbox.ident.secret_key.sign(data).serialise());
+    *c_sig   = CBoxVec::from_vec(sig);
+    CBoxResult::Success
+}
+
+/// Verify a detached signature produced by `cbox_sign` against a
+/// serialised `Identity` — the same wire format produced by
+/// `cbox_identity_copy_public` (an `Identity::Pub`), or by
+/// `cbox_identity_copy` (an `Identity::Sec`, whose public half is used
+/// here) for callers willing to hand over their own secret key.
+#[no_mangle]
+pub unsafe extern
+fn cbox_verify(c_pub:      *const uint8_t,
+              c_pub_len:  size_t,
+              c_data:     *const uint8_t,
+              c_data_len: size_t,
+              c_sig:      *const uint8_t,
+              c_sig_len:  size_t) -> CBoxResult
+{
+    let key = match try_unwrap!(dec_raw(&c_pub, c_pub_len as usize, Identity::deserialise)) {
+        Identity::Sec(i) => i.into_owned().public_key,
+        Identity::Pub(p) => p.into_owned()
+    };
+    let data = slice::from_raw_parts(c_data, c_data_len as usize);
+    let sig  = try_unwrap!(dec_raw(&c_sig, c_sig_len as usize, keys::Signature::deserialise));
+    if key.verify(&sig, data) {
+        CBoxResult::Success
+    } else {
+        CBoxResult::InvalidSignature
+    }
+}
+
+// Migration //////////////////////////////////////////////////////////////////
+
+/// Format of the blob produced by `cbox_export` / consumed by
+/// `cbox_import`: a version byte followed by length-prefixed chunks, so
+/// the layout can grow without breaking readers of older blobs.
+const EXPORT_BLOB_VERSION: u8 = 1;
+
+fn write_u32(buf: &mut Vec<u8>, n: u32) {
+    buf.push((n >> 24) as u8);
+    buf.push((n >> 16) as u8);
+    buf.push((n >> 8)  as u8);
+    buf.push(n as u8);
+}
+
+fn write_chunk(buf: &mut Vec<u8>, bytes: &[u8]) {
+    write_u32(buf, bytes.len() as u32);
+    buf.extend_from_slice(bytes);
+}
+
+fn read_u32(bytes: &[u8], pos: &mut usize) -> Result<u32, CBoxResult> {
+    if bytes.len() < *pos + 4 {
+        return Err(CBoxResult::DecodeError)
+    }
+    let n = ((bytes[*pos]     as u32) << 24)
+          | ((bytes[*pos + 1] as u32) << 16)
+          | ((bytes[*pos + 2] as u32) << 8)
+          |  (bytes[*pos + 3] as u32);
+    *pos += 4;
+    Ok(n)
+}
+
+fn read_chunk<'a>(bytes: &'a [u8], pos: &mut usize) -> Result<&'a [u8], CBoxResult> {
+    let len = try!(read_u32(bytes, pos)) as usize;
+    if bytes.len() < *pos + len {
+        return Err(CBoxResult::DecodeError)
+    }
+    let chunk = &bytes[*pos .. *pos + len];
+    *pos += len;
+    Ok(chunk)
+}
+
+/// Serialise the box's identity (in `Complete` mode), every remaining
+/// prekey and every saved session into a single blob, for moving a box
+/// to another device in one step via `cbox_import`.
+#[no_mangle]
+pub unsafe extern
+fn cbox_export(c_box: *mut CBox, c_blob: *mut *mut CBoxVec) -> CBoxResult {
+    let cbox = &*c_box;
+
+    let mut blob = vec![EXPORT_BLOB_VERSION];
+
+    let ident = try_unwrap!(Identity::Sec(Cow::Borrowed(&cbox.ident)).serialise());
+    write_chunk(&mut blob, &ident);
+
+    let prekeys = try_unwrap!(cbox.store.prekeys());
+    write_u32(&mut blob, prekeys.len() as u32);
+    for pk in &prekeys {
+        write_chunk(&mut blob, &try_unwrap!(pk.serialise()));
+    }
+
+    let sids = try_unwrap!(cbox.store.session_ids());
+    write_u32(&mut blob, sids.len() as u32);
+    for sid in &sids {
+        if let Some(sess) = try_unwrap!(cbox.store.load_session(&cbox.ident, sid)) {
+            write_chunk(&mut blob, sid.as_bytes());
+            write_chunk(&mut blob, &try_unwrap!(sess.serialise()));
+        }
+    }
+
+    *c_blob = CBoxVec::from_vec(blob);
+    CBoxResult::Success
+}
+
+/// Decode a blob produced by `cbox_export` into `store`, which must be
+/// freshly created (or at least hold no identity but the one about to
+/// be imported). Shared by `cbox_import` and `cbox_import_memory` so
+/// every backend restores a blob the same way.
+fn import_into<S: Store<Error=StorageError>>(store: S, bytes: &[u8]) -> Result<CBox, CBoxResult> {
+    if bytes.is_empty() || bytes[0] != EXPORT_BLOB_VERSION {
+        return Err(CBoxResult::DecodeError)
+    }
+    let mut pos = 1usize;
+
+    let ident_bytes = try!(read_chunk(bytes, &mut pos));
+    let ident = match try!(Identity::deserialise(ident_bytes)) {
+        Identity::Sec(i) => i.into_owned(),
+        Identity::Pub(_) => return Err(CBoxResult::IdentityError)
+    };
+
+    match try!(store.load_identity()) {
+        Some(Identity::Sec(local)) if local.public_key != ident.public_key => return Err(CBoxResult::IdentityError),
+        Some(Identity::Pub(local)) if *local != ident.public_key          => return Err(CBoxResult::IdentityError),
+        _ => try!(store.save_identity(&Identity::Sec(Cow::Borrowed(&ident))))
+    }
+
+    let num_prekeys = try!(read_u32(bytes, &mut pos));
+    for _ in 0 .. num_prekeys {
+        let pk_bytes = try!(read_chunk(bytes, &mut pos));
+        let pk       = try!(PreKey::deserialise(pk_bytes));
+        try!(store.add_prekey(&pk));
+    }
+
+    let num_sessions = try!(read_u32(bytes, &mut pos));
+    for _ in 0 .. num_sessions {
+        let sid_bytes  = try!(read_chunk(bytes, &mut pos));
+        let sid        = try!(str::from_utf8(sid_bytes));
+        let sess_bytes = try!(read_chunk(bytes, &mut pos));
+        let sess       = try!(Session::deserialise(&ident, sess_bytes));
+        try!(store.save_session(sid, &sess));
+    }
+
+    Ok(CBox { store: Box::new(store), ident: ident })
+}
+
+/// Rebuild a `FileStore` at `c_path` from a blob produced by
+/// `cbox_export`. Fails with `CBoxResult::IdentityError` if `c_path`
+/// already holds a different identity.
+#[no_mangle]
+pub unsafe extern
+fn cbox_import(c_path:     *const c_char,
+              c_blob:     *const uint8_t,
+              c_blob_len: size_t,
+              c_box:      *mut *mut CBox) -> CBoxResult
+{
+    proteus::init();
+    let name  = try_unwrap!(str::from_utf8(CStr::from_ptr(c_path).to_bytes()));
+    let path  = Path::new(name);
+    let bytes = slice::from_raw_parts(c_blob, c_blob_len as usize);
+    let store = try_unwrap!(FileStore::new(path));
+
+    match import_into(store, bytes) {
+        Ok(cbox) => {
+            *c_box = Box::into_raw(Box::new(cbox));
+            CBoxResult::Success
+        }
+        Err(e) => e
+    }
+}
+
+/// Memory-backed counterpart to `cbox_import`, for restoring an
+/// exported blob into a throwaway `MemStore` rather than a `FileStore`.
+#[no_mangle]
+pub unsafe extern
+fn cbox_import_memory(c_blob: *const uint8_t, c_blob_len: size_t, c_box: *mut *mut CBox) -> CBoxResult {
+    proteus::init();
+    let bytes = slice::from_raw_parts(c_blob, c_blob_len as usize);
+    let store = MemStore::new();
+
+    match import_into(store, bytes) {
+        Ok(cbox) => {
+            *c_box = Box::into_raw(Box::new(cbox));
+            CBoxResult::Success
+        }
+        Err(e) => e
+    }
+}
+
 // CBoxVec /////////////////////////////////////////////////////////////////////
 
 #[no_mangle]
@@ -374,7 +760,7 @@ pub unsafe extern fn cbox_vec_len(v: *const CBoxVec) -> size_t {
 
 #[repr(C)]
 #[no_mangle]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum CBoxResult {
     Success               = 0,
     StorageError          = 1,
@@ -390,7 +776,10 @@ pub enum CBoxResult {
     NulError              = 11,
     EncodeError           = 12,
     IdentityError         = 13,
-    PreKeyNotFound        = 14
+    PreKeyNotFound        = 14,
+    InvalidPhrase         = 15,
+    InvalidPrefix         = 16,
+    PrefixNotFound        = 17
 }
 
 impl<E: Error> From<DecryptError<E>> for CBoxResult {
@@ -460,3 +849,260 @@ unsafe fn dec_raw<A, F>(ptr: & *const c_uchar, len: usize, f: F) -> Result<A, De
 where F: Fn(&[u8]) -> Result<A, DecodeError> {
     f(slice::from_raw_parts(*ptr, len))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::ptr;
+
+    fn temp_path(tag: &str) -> CString {
+        let dir = env::temp_dir().join(format!("cryptobox-test-{}-{}", tag, process::id()));
+        CString::new(dir.to_str().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn exports_and_reimports_a_file_backed_box() {
+        unsafe {
+            proteus::init();
+
+            let src_path = temp_path("export-src");
+            let mut src: *mut CBox = ptr::null_mut();
+            assert_eq!(CBoxResult::Success, cbox_file_open(src_path.as_ptr(), &mut src));
+
+            let mut bundle: *mut CBoxVec = ptr::null_mut();
+            assert_eq!(CBoxResult::Success, cbox_new_prekey(src, 1, &mut bundle));
+            cbox_vec_free(bundle);
+
+            let mut blob: *mut CBoxVec = ptr::null_mut();
+            assert_eq!(CBoxResult::Success, cbox_export(src, &mut blob));
+
+            let dst_path = temp_path("export-dst");
+            let mut dst: *mut CBox = ptr::null_mut();
+            let result = cbox_import(dst_path.as_ptr(),
+                                      cbox_vec_data(blob),
+                                      cbox_vec_len(blob),
+                                      &mut dst);
+            assert_eq!(CBoxResult::Success, result);
+
+            assert_eq!((*src).ident.public_key, (*dst).ident.public_key);
+            assert_eq!((*src).store.prekeys().unwrap().len(),
+                       (*dst).store.prekeys().unwrap().len());
+
+            cbox_vec_free(blob);
+            cbox_close(src);
+            cbox_close(dst);
+        }
+    }
+
+    #[test]
+    fn refuses_to_import_onto_a_different_identity() {
+        unsafe {
+            proteus::init();
+
+            let src_path = temp_path("mismatch-src");
+            let mut src: *mut CBox = ptr::null_mut();
+            assert_eq!(CBoxResult::Success, cbox_file_open(src_path.as_ptr(), &mut src));
+
+            let mut blob: *mut CBoxVec = ptr::null_mut();
+            assert_eq!(CBoxResult::Success, cbox_export(src, &mut blob));
+
+            let dst_path = temp_path("mismatch-dst");
+            let mut other: *mut CBox = ptr::null_mut();
+            assert_eq!(CBoxResult::Success, cbox_file_open(dst_path.as_ptr(), &mut other));
+            cbox_close(other);
+
+            let mut dst: *mut CBox = ptr::null_mut();
+            let result = cbox_import(dst_path.as_ptr(),
+                                      cbox_vec_data(blob),
+                                      cbox_vec_len(blob),
+                                      &mut dst);
+            assert_eq!(CBoxResult::IdentityError, result);
+
+            cbox_vec_free(blob);
+            cbox_close(src);
+        }
+    }
+
+    #[test]
+    fn signs_and_verifies_a_round_trip() {
+        unsafe {
+            proteus::init();
+
+            let path = temp_path("sign-roundtrip");
+            let mut cbox: *mut CBox = ptr::null_mut();
+            assert_eq!(CBoxResult::Success, cbox_file_open(path.as_ptr(), &mut cbox));
+
+            let data = b"a profile blob worth authenticating";
+            let mut sig: *mut CBoxVec = ptr::null_mut();
+            assert_eq!(CBoxResult::Success, cbox_sign(cbox, data.as_ptr(), data.len(), &mut sig));
+
+            let mut pub_ident: *mut CBoxVec = ptr::null_mut();
+            assert_eq!(CBoxResult::Success, cbox_identity_copy_public(cbox, &mut pub_ident));
+
+            let result = cbox_verify(cbox_vec_data(pub_ident), cbox_vec_len(pub_ident),
+                                      data.as_ptr(), data.len(),
+                                      cbox_vec_data(sig), cbox_vec_len(sig));
+            assert_eq!(CBoxResult::Success, result);
+
+            cbox_vec_free(sig);
+            cbox_vec_free(pub_ident);
+            cbox_close(cbox);
+        }
+    }
+
+    #[test]
+    fn rejects_a_signature_over_tampered_data() {
+        unsafe {
+            proteus::init();
+
+            let path = temp_path("sign-tampered");
+            let mut cbox: *mut CBox = ptr::null_mut();
+            assert_eq!(CBoxResult::Success, cbox_file_open(path.as_ptr(), &mut cbox));
+
+            let data = b"a profile blob worth authenticating";
+            let mut sig: *mut CBoxVec = ptr::null_mut();
+            assert_eq!(CBoxResult::Success, cbox_sign(cbox, data.as_ptr(), data.len(), &mut sig));
+
+            let mut pub_ident: *mut CBoxVec = ptr::null_mut();
+            assert_eq!(CBoxResult::Success, cbox_identity_copy_public(cbox, &mut pub_ident));
+
+            let tampered = b"a profile blob worth tampering";
+            let result = cbox_verify(cbox_vec_data(pub_ident), cbox_vec_len(pub_ident),
+                                      tampered.as_ptr(), tampered.len(),
+                                      cbox_vec_data(sig), cbox_vec_len(sig));
+            assert_eq!(CBoxResult::InvalidSignature, result);
+
+            cbox_vec_free(sig);
+            cbox_vec_free(pub_ident);
+            cbox_close(cbox);
+        }
+    }
+
+    #[test]
+    fn rejects_a_signature_checked_against_the_wrong_key() {
+        unsafe {
+            proteus::init();
+
+            let path = temp_path("sign-wrong-key");
+            let mut cbox: *mut CBox = ptr::null_mut();
+            assert_eq!(CBoxResult::Success, cbox_file_open(path.as_ptr(), &mut cbox));
+
+            let other_path = temp_path("sign-wrong-key-other");
+            let mut other: *mut CBox = ptr::null_mut();
+            assert_eq!(CBoxResult::Success, cbox_file_open(other_path.as_ptr(), &mut other));
+
+            let data = b"a profile blob worth authenticating";
+            let mut sig: *mut CBoxVec = ptr::null_mut();
+            assert_eq!(CBoxResult::Success, cbox_sign(cbox, data.as_ptr(), data.len(), &mut sig));
+
+            let mut other_pub_ident: *mut CBoxVec = ptr::null_mut();
+            assert_eq!(CBoxResult::Success, cbox_identity_copy_public(other, &mut other_pub_ident));
+
+            let result = cbox_verify(cbox_vec_data(other_pub_ident), cbox_vec_len(other_pub_ident),
+                                      data.as_ptr(), data.len(),
+                                      cbox_vec_data(sig), cbox_vec_len(sig));
+            assert_eq!(CBoxResult::InvalidSignature, result);
+
+            cbox_vec_free(sig);
+            cbox_vec_free(other_pub_ident);
+            cbox_close(cbox);
+            cbox_close(other);
+        }
+    }
+
+    #[test]
+    fn finds_an_identity_matching_a_short_prefix() {
+        unsafe {
+            let prefix = CString::new("0").unwrap();
+            let mut identity: *mut CBoxVec = ptr::null_mut();
+            let result = cbox_identity_generate_prefix(prefix.as_ptr(), 1, 4, 0, &mut identity);
+            assert_eq!(CBoxResult::Success, result);
+
+            let bytes = slice::from_raw_parts(cbox_vec_data(identity), cbox_vec_len(identity));
+            match Identity::deserialise(bytes).unwrap() {
+                Identity::Sec(ident) => assert!(ident.public_key.fingerprint().starts_with("0")),
+                Identity::Pub(_)     => panic!("expected Identity::Sec")
+            }
+
+            cbox_vec_free(identity);
+        }
+    }
+
+    #[test]
+    fn rejects_a_prefix_with_a_non_hex_character() {
+        unsafe {
+            let prefix = CString::new("zz").unwrap();
+            let mut identity: *mut CBoxVec = ptr::null_mut();
+            let result = cbox_identity_generate_prefix(prefix.as_ptr(), 2, 1, 0, &mut identity);
+            assert_eq!(CBoxResult::InvalidPrefix, result);
+        }
+    }
+
+    #[test]
+    fn rejects_a_prefix_longer_than_a_fingerprint() {
+        unsafe {
+            let too_long = CString::new("0".repeat(FINGERPRINT_LEN + 1)).unwrap();
+            let mut identity: *mut CBoxVec = ptr::null_mut();
+            let result = cbox_identity_generate_prefix(too_long.as_ptr(),
+                                                        FINGERPRINT_LEN + 1,
+                                                        1, 0, &mut identity);
+            assert_eq!(CBoxResult::InvalidPrefix, result);
+        }
+    }
+
+    #[test]
+    fn gives_up_on_an_unreachable_prefix_once_attempts_are_exhausted() {
+        unsafe {
+            let prefix = CString::new("deadbeefdeadbeef").unwrap();
+            let mut identity: *mut CBoxVec = ptr::null_mut();
+            let result = cbox_identity_generate_prefix(prefix.as_ptr(), 16, 1, 1, &mut identity);
+            assert_eq!(CBoxResult::PrefixNotFound, result);
+        }
+    }
+
+    #[test]
+    fn opens_a_memory_box_and_reconciles_the_full_identity() {
+        unsafe {
+            proteus::init();
+
+            let ident = IdentityKeyPair::new();
+            let bytes = Identity::Sec(Cow::Borrowed(&ident)).serialise().unwrap();
+
+            let mut cbox: *mut CBox = ptr::null_mut();
+            let result = cbox_open_memory(bytes.as_ptr(), bytes.len(),
+                                           CBoxIdentityMode::Complete, &mut cbox);
+            assert_eq!(CBoxResult::Success, result);
+            assert_eq!(ident.public_key, (*cbox).ident.public_key);
+
+            match (*cbox).store.load_identity().unwrap() {
+                Some(Identity::Sec(saved)) => assert_eq!(ident.public_key, saved.public_key),
+                _ => panic!("expected a saved Identity::Sec")
+            }
+
+            cbox_close(cbox);
+        }
+    }
+
+    #[test]
+    fn opens_a_memory_box_in_public_mode_without_persisting_the_secret_key() {
+        unsafe {
+            proteus::init();
+
+            let ident = IdentityKeyPair::new();
+            let bytes = Identity::Sec(Cow::Borrowed(&ident)).serialise().unwrap();
+
+            let mut cbox: *mut CBox = ptr::null_mut();
+            let result = cbox_open_memory(bytes.as_ptr(), bytes.len(),
+                                           CBoxIdentityMode::Public, &mut cbox);
+            assert_eq!(CBoxResult::Success, result);
+
+            match (*cbox).store.load_identity().unwrap() {
+                Some(Identity::Pub(saved)) => assert_eq!(ident.public_key, *saved),
+                _ => panic!("expected a saved Identity::Pub")
+            }
+
+            cbox_close(cbox);
+        }
+    }
+}
@@ -0,0 +1,172 @@
+// This Source Code Form is subject to the terms of
+// the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You
+// can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! BIP39-style mnemonic encoding of entropy and derivation of a seed from
+//! a mnemonic + passphrase, used by `cbox_file_open_from_phrase` to turn a
+//! recovery phrase into a deterministic long-term identity.
+
+use crypto::hmac::Hmac;
+use crypto::pbkdf2::pbkdf2;
+use crypto::sha2::{Sha256, Sha512};
+use crypto::digest::Digest;
+
+/// The 2048-word list used to encode 11-bit groups as words.
+static WORDLIST: &'static str = include_str!("wordlist_en.txt");
+
+#[derive(Debug)]
+pub enum MnemonicError {
+    /// The entropy was not 128, 160, 192, 224 or 256 bits long.
+    InvalidEntropySize,
+    /// The phrase did not contain (entropy + checksum) bits encoded as
+    /// a whole number of words, or contained a word outside the list.
+    InvalidPhrase
+}
+
+fn words() -> Vec<&'static str> {
+    WORDLIST.lines().collect()
+}
+
+/// Encode `entropy` (16-32 bytes) plus its SHA-256 checksum as a
+/// space-separated mnemonic phrase.
+pub fn entropy_to_mnemonic(entropy: &[u8]) -> Result<String, MnemonicError> {
+    let ent_bits = entropy.len() * 8;
+    match ent_bits {
+        128 | 160 | 192 | 224 | 256 => (),
+        _ => return Err(MnemonicError::InvalidEntropySize)
+    }
+
+    let mut digest = Sha256::new();
+    digest.input(entropy);
+    let mut checksum = [0u8; 32];
+    digest.result(&mut checksum);
+
+    let cs_bits = ent_bits / 32;
+
+    // Concatenate entropy || checksum as a bit string, then split into
+    // 11-bit groups, each indexing one word.
+    let mut bits = Vec::with_capacity(ent_bits + cs_bits);
+    for byte in entropy {
+        for i in 0..8 {
+            bits.push((byte >> (7 - i)) & 1 == 1);
+        }
+    }
+    for i in 0..cs_bits {
+        let byte = checksum[i / 8];
+        let bit  = (byte >> (7 - (i % 8))) & 1 == 1;
+        bits.push(bit);
+    }
+
+    let wordlist = words();
+    let mut phrase = String::new();
+    for group in bits.chunks(11) {
+        let mut idx = 0usize;
+        for &bit in group {
+            idx = (idx << 1) | (bit as usize);
+        }
+        if !phrase.is_empty() {
+            phrase.push(' ');
+        }
+        phrase.push_str(wordlist[idx]);
+    }
+    Ok(phrase)
+}
+
+/// Validate `phrase` against the word list and checksum.
+pub fn validate_mnemonic(phrase: &str) -> Result<(), MnemonicError> {
+    let wordlist = words();
+    let tokens: Vec<&str> = phrase.split_whitespace().collect();
+    match tokens.len() {
+        12 | 15 | 18 | 21 | 24 => (),
+        _ => return Err(MnemonicError::InvalidPhrase)
+    }
+
+    let mut bits = Vec::with_capacity(tokens.len() * 11);
+    for token in &tokens {
+        let idx = match wordlist.iter().position(|w| w == token) {
+            Some(i) => i,
+            None    => return Err(MnemonicError::InvalidPhrase)
+        };
+        for i in 0..11 {
+            bits.push((idx >> (10 - i)) & 1 == 1);
+        }
+    }
+
+    let total_bits = bits.len();
+    let ent_bits    = total_bits * 32 / 33;
+    let cs_bits     = total_bits - ent_bits;
+
+    let mut entropy = vec![0u8; ent_bits / 8];
+    for (i, chunk) in bits[..ent_bits].chunks(8).enumerate() {
+        let mut byte = 0u8;
+        for &bit in chunk {
+            byte = (byte << 1) | (bit as u8);
+        }
+        entropy[i] = byte;
+    }
+
+    let mut digest = Sha256::new();
+    digest.input(&entropy);
+    let mut checksum = [0u8; 32];
+    digest.result(&mut checksum);
+
+    for i in 0..cs_bits {
+        let expect = (checksum[i / 8] >> (7 - (i % 8))) & 1 == 1;
+        if expect != bits[ent_bits + i] {
+            return Err(MnemonicError::InvalidPhrase)
+        }
+    }
+    Ok(())
+}
+
+/// Derive the 64-byte PBKDF2-HMAC-SHA512 seed from `phrase` and
+/// `passphrase`, per the standard mnemonic-to-seed scheme.
+pub fn mnemonic_to_seed(phrase: &str, passphrase: &str) -> [u8; 64] {
+    let mut salt = String::with_capacity(8 + passphrase.len());
+    salt.push_str("mnemonic");
+    salt.push_str(passphrase);
+
+    let mut mac = Hmac::new(Sha512::new(), phrase.as_bytes());
+    let mut seed = [0u8; 64];
+    pbkdf2(&mut mac, salt.as_bytes(), 2048, &mut seed);
+    seed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_entropy_through_a_mnemonic() {
+        for &ent_bits in &[128, 160, 192, 224, 256] {
+            let entropy = vec![0x7au8; ent_bits / 8];
+            let phrase  = entropy_to_mnemonic(&entropy).unwrap();
+            assert!(validate_mnemonic(&phrase).is_ok());
+        }
+    }
+
+    #[test]
+    fn rejects_an_entropy_size_off_the_standard_steps() {
+        assert!(entropy_to_mnemonic(&[0u8; 17]).is_err());
+    }
+
+    #[test]
+    fn rejects_a_phrase_with_a_tampered_word() {
+        let phrase = entropy_to_mnemonic(&[0x42u8; 16]).unwrap();
+        let mut words: Vec<&str> = phrase.split_whitespace().collect();
+        let last = words.len() - 1;
+        words[last] = if words[last] == "zoo" { "wood" } else { "zoo" };
+        let tampered = words.join(" ");
+        assert!(validate_mnemonic(&tampered).is_err());
+    }
+
+    #[test]
+    fn derives_the_same_seed_for_the_same_phrase_and_passphrase() {
+        let phrase = entropy_to_mnemonic(&[0x99u8; 16]).unwrap();
+        let a = mnemonic_to_seed(&phrase, "");
+        let b = mnemonic_to_seed(&phrase, "");
+        assert_eq!(&a[..], &b[..]);
+        assert_ne!(&a[..], &mnemonic_to_seed(&phrase, "tr3z0r")[..]);
+    }
+}
@@ -0,0 +1,88 @@
+// This Source Code Form is subject to the terms of
+// the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You
+// can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The storage abstraction underlying `CBox`: persisting the long-term
+//! identity, issued prekeys and established sessions. `store::file::FileStore`
+//! and `store::mem::MemStore` are the two backends shipped with this crate.
+
+use identity::Identity;
+use proteus::keys::{IdentityKeyPair, PreKey};
+use proteus::session::{PreKeyStore, Session};
+use proteus::{DecodeError, EncodeError};
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+pub type StorageResult<A> = Result<A, StorageError>;
+
+/// Everything a `CBox` needs from a storage backend: the long-term
+/// identity, issued prekeys (via the `PreKeyStore` supertrait) and
+/// established sessions.
+pub trait Store: PreKeyStore<Error=StorageError> {
+    fn load_identity<'s>(&'s self) -> StorageResult<Option<Identity<'s>>>;
+    fn save_identity(&self, ident: &Identity) -> StorageResult<()>;
+
+    fn add_prekey(&self, prekey: &PreKey) -> StorageResult<()>;
+
+    /// Every prekey currently held by the store. Prekeys already deleted
+    /// through `PreKeyStore::remove` are not included.
+    fn prekeys(&self) -> StorageResult<Vec<PreKey>>;
+
+    fn load_session<'s>(&self, ident: &'s IdentityKeyPair, sid: &str) -> StorageResult<Option<Session<'s>>>;
+    fn save_session(&self, sid: &str, session: &Session) -> StorageResult<()>;
+    fn delete_session(&self, sid: &str) -> StorageResult<()>;
+
+    /// The session IDs of every session currently saved in the store.
+    fn session_ids(&self) -> StorageResult<Vec<String>>;
+}
+
+#[derive(Debug)]
+pub enum StorageError {
+    Io(io::Error),
+    Encode(EncodeError),
+    Decode(DecodeError)
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StorageError::Io(ref e)     => write!(f, "I/O error: {}", e),
+            StorageError::Encode(ref e) => write!(f, "Encode error: {}", e),
+            StorageError::Decode(ref e) => write!(f, "Decode error: {}", e)
+        }
+    }
+}
+
+impl Error for StorageError {
+    fn description(&self) -> &str {
+        "storage error"
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            StorageError::Io(ref e)     => Some(e),
+            StorageError::Encode(ref e) => Some(e),
+            StorageError::Decode(ref e) => Some(e)
+        }
+    }
+}
+
+impl From<io::Error> for StorageError {
+    fn from(e: io::Error) -> StorageError {
+        StorageError::Io(e)
+    }
+}
+
+impl From<EncodeError> for StorageError {
+    fn from(e: EncodeError) -> StorageError {
+        StorageError::Encode(e)
+    }
+}
+
+impl From<DecodeError> for StorageError {
+    fn from(e: DecodeError) -> StorageError {
+        StorageError::Decode(e)
+    }
+}
@@ -0,0 +1,131 @@
+// This Source Code Form is subject to the terms of
+// the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You
+// can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! An on-disk `Store`: identity, prekeys and sessions each round-trip
+//! through their `serialise`/`deserialise` methods into their own file
+//! below the store's root directory.
+
+use identity::Identity;
+use proteus::keys::{IdentityKeyPair, PreKey, PreKeyId};
+use proteus::session::{PreKeyStore, Session};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use store::api::{Store, StorageError, StorageResult};
+
+pub struct FileStore {
+    root: PathBuf
+}
+
+impl FileStore {
+    pub fn new(root: &Path) -> StorageResult<FileStore> {
+        try!(fs::create_dir_all(root));
+        try!(fs::create_dir_all(root.join("prekeys")));
+        try!(fs::create_dir_all(root.join("sessions")));
+        Ok(FileStore { root: root.to_path_buf() })
+    }
+
+    fn identity_path(&self) -> PathBuf {
+        self.root.join("identity")
+    }
+
+    fn prekey_path(&self, id: PreKeyId) -> PathBuf {
+        self.root.join("prekeys").join(id.value().to_string())
+    }
+
+    fn session_path(&self, sid: &str) -> PathBuf {
+        self.root.join("sessions").join(sid)
+    }
+}
+
+fn read_file(path: &Path) -> StorageResult<Vec<u8>> {
+    let mut file = try!(File::open(path));
+    let mut bytes = Vec::new();
+    try!(file.read_to_end(&mut bytes));
+    Ok(bytes)
+}
+
+fn write_file(path: &Path, bytes: &[u8]) -> StorageResult<()> {
+    let mut file = try!(File::create(path));
+    try!(file.write_all(bytes));
+    Ok(())
+}
+
+impl PreKeyStore for FileStore {
+    type Error = StorageError;
+
+    fn prekey(&mut self, id: PreKeyId) -> StorageResult<Option<PreKey>> {
+        let path = self.prekey_path(id);
+        if !path.exists() {
+            return Ok(None)
+        }
+        Ok(Some(try!(PreKey::deserialise(&try!(read_file(&path))))))
+    }
+
+    fn remove(&mut self, id: PreKeyId) -> StorageResult<()> {
+        let path = self.prekey_path(id);
+        if path.exists() {
+            try!(fs::remove_file(path));
+        }
+        Ok(())
+    }
+}
+
+impl Store for FileStore {
+    fn load_identity<'s>(&'s self) -> StorageResult<Option<Identity<'s>>> {
+        let path = self.identity_path();
+        if !path.exists() {
+            return Ok(None)
+        }
+        Ok(Some(try!(Identity::deserialise(&try!(read_file(&path))))))
+    }
+
+    fn save_identity(&self, ident: &Identity) -> StorageResult<()> {
+        write_file(&self.identity_path(), &try!(ident.serialise()))
+    }
+
+    fn add_prekey(&self, prekey: &PreKey) -> StorageResult<()> {
+        write_file(&self.prekey_path(prekey.key_id), &try!(prekey.serialise()))
+    }
+
+    fn prekeys(&self) -> StorageResult<Vec<PreKey>> {
+        let mut prekeys = Vec::new();
+        for entry in try!(fs::read_dir(self.root.join("prekeys"))) {
+            let bytes = try!(read_file(&try!(entry).path()));
+            prekeys.push(try!(PreKey::deserialise(&bytes)));
+        }
+        Ok(prekeys)
+    }
+
+    fn load_session<'s>(&self, ident: &'s IdentityKeyPair, sid: &str) -> StorageResult<Option<Session<'s>>> {
+        let path = self.session_path(sid);
+        if !path.exists() {
+            return Ok(None)
+        }
+        Ok(Some(try!(Session::deserialise(ident, &try!(read_file(&path))))))
+    }
+
+    fn save_session(&self, sid: &str, session: &Session) -> StorageResult<()> {
+        write_file(&self.session_path(sid), &try!(session.serialise()))
+    }
+
+    fn delete_session(&self, sid: &str) -> StorageResult<()> {
+        let path = self.session_path(sid);
+        if path.exists() {
+            try!(fs::remove_file(path));
+        }
+        Ok(())
+    }
+
+    fn session_ids(&self) -> StorageResult<Vec<String>> {
+        let mut ids = Vec::new();
+        for entry in try!(fs::read_dir(self.root.join("sessions"))) {
+            if let Some(name) = try!(entry).file_name().to_str() {
+                ids.push(name.to_string());
+            }
+        }
+        Ok(ids)
+    }
+}
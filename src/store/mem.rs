@@ -0,0 +1,101 @@
+// This Source Code Form is subject to the terms of
+// the Mozilla Public License, v. 2.0. If a copy of
+// the MPL was not distributed with this file, You
+// can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! An in-memory `Store`, for callers that want a `CBox` without touching
+//! disk: unit tests, short-lived sessions, and sandboxes with no
+//! writable filesystem.
+
+use identity::Identity;
+use proteus::keys::{IdentityKeyPair, PreKey, PreKeyId, PublicKey};
+use proteus::session::{PreKeyStore, Session};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use store::api::{Store, StorageError, StorageResult};
+
+enum StoredIdentity {
+    Sec(IdentityKeyPair),
+    Pub(PublicKey)
+}
+
+pub struct MemStore {
+    identity: RefCell<Option<StoredIdentity>>,
+    prekeys:  RefCell<HashMap<u16, PreKey>>,
+    sessions: RefCell<HashMap<String, Vec<u8>>>
+}
+
+impl MemStore {
+    pub fn new() -> MemStore {
+        MemStore {
+            identity: RefCell::new(None),
+            prekeys:  RefCell::new(HashMap::new()),
+            sessions: RefCell::new(HashMap::new())
+        }
+    }
+}
+
+impl PreKeyStore for MemStore {
+    type Error = StorageError;
+
+    fn prekey(&mut self, id: PreKeyId) -> StorageResult<Option<PreKey>> {
+        Ok(self.prekeys.borrow().get(&id.value()).cloned())
+    }
+
+    fn remove(&mut self, id: PreKeyId) -> StorageResult<()> {
+        self.prekeys.borrow_mut().remove(&id.value());
+        Ok(())
+    }
+}
+
+impl Store for MemStore {
+    fn load_identity<'s>(&'s self) -> StorageResult<Option<Identity<'s>>> {
+        let ident = match *self.identity.borrow() {
+            Some(StoredIdentity::Sec(ref i)) => Some(Identity::Sec(Cow::Owned(i.clone()))),
+            Some(StoredIdentity::Pub(ref p)) => Some(Identity::Pub(Cow::Owned(p.clone()))),
+            None                             => None
+        };
+        Ok(ident)
+    }
+
+    fn save_identity(&self, ident: &Identity) -> StorageResult<()> {
+        let stored = match *ident {
+            Identity::Sec(ref i) => StoredIdentity::Sec(i.clone().into_owned()),
+            Identity::Pub(ref p) => StoredIdentity::Pub(p.clone().into_owned())
+        };
+        *self.identity.borrow_mut() = Some(stored);
+        Ok(())
+    }
+
+    fn add_prekey(&self, prekey: &PreKey) -> StorageResult<()> {
+        self.prekeys.borrow_mut().insert(prekey.key_id.value(), prekey.clone());
+        Ok(())
+    }
+
+    fn load_session<'s>(&self, ident: &'s IdentityKeyPair, sid: &str) -> StorageResult<Option<Session<'s>>> {
+        match self.sessions.borrow().get(sid) {
+            Some(bytes) => Ok(Some(try!(Session::deserialise(ident, bytes)))),
+            None        => Ok(None)
+        }
+    }
+
+    fn save_session(&self, sid: &str, session: &Session) -> StorageResult<()> {
+        let bytes = try!(session.serialise());
+        self.sessions.borrow_mut().insert(sid.to_string(), bytes);
+        Ok(())
+    }
+
+    fn delete_session(&self, sid: &str) -> StorageResult<()> {
+        self.sessions.borrow_mut().remove(sid);
+        Ok(())
+    }
+
+    fn prekeys(&self) -> StorageResult<Vec<PreKey>> {
+        Ok(self.prekeys.borrow().values().cloned().collect())
+    }
+
+    fn session_ids(&self) -> StorageResult<Vec<String>> {
+        Ok(self.sessions.borrow().keys().cloned().collect())
+    }
+}